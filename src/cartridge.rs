@@ -106,6 +106,34 @@ impl Cartridge {
     pub fn file_system(&mut self) -> io::Result<Filesystem> {
         Filesystem::from_rom(&mut self.inner)
     }
+
+    /// Verifies the NitroROM header against its stored CRC16 checksum,
+    /// computed over the first `0x15E` bytes of the header.
+    pub fn header_crc(&self) -> io::Result<bool> {
+        let mut header = [0u8; 0x15E];
+        self.inner.read_exact_at(&mut header, 0)?;
+        let expected = self.inner.read_u16_at(0x15E)?;
+        Ok(header_crc16(&header) == expected)
+    }
+}
+
+/// Computes the CRC16 checksum used by the NitroROM header, over `data`.
+fn header_crc16(data: &[u8]) -> u16 {
+    const TABLE: [u16; 8] = [
+        0xC0C1, 0xC181, 0xC301, 0xC601, 0xCC01, 0xD801, 0xF001, 0xA001,
+    ];
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= byte as u16;
+        let low = crc & 0xFF;
+        crc >>= 8;
+        for (bit, &entry) in TABLE.iter().enumerate() {
+            if (low >> bit) & 1 != 0 {
+                crc ^= entry;
+            }
+        }
+    }
+    crc
 }
 
 /// Returns early with an [`io::Error`].
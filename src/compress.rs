@@ -0,0 +1,87 @@
+use crate::cartridge::ReadBytes;
+use crate::io_bail;
+use std::io;
+use std::io::Read;
+
+/// The leading magic byte of a BIOS LZ77 (type `0x10`) compressed stream.
+const LZ77_MAGIC: u8 = 0x10;
+
+/// A reader that transparently decompresses a BIOS LZ77 (type `0x10`)
+/// compressed stream.
+///
+/// The header consists of the magic byte followed by a 3-byte little-endian
+/// decompressed size. The body is a sequence of blocks, each starting with
+/// a flag byte whose bits are processed MSB-first: a `0` bit copies one
+/// literal byte, while a `1` bit reads two further bytes encoding a
+/// length/displacement pair referring back into the already-produced
+/// output.
+///
+/// Decompression happens eagerly in [`DecompressReader::new`]; reads then
+/// drain the resulting buffer.
+pub struct DecompressReader {
+    cursor: io::Cursor<Vec<u8>>,
+}
+
+impl DecompressReader {
+    /// Attempts to wrap a reader whose contents start with a LZ77 header.
+    ///
+    /// Returns an error if the leading magic byte doesn't match the LZ77
+    /// compression type, so that callers can fall back to reading the file
+    /// uncompressed.
+    pub fn new<R: Read>(mut reader: R) -> io::Result<Self> {
+        let magic = reader.read_u8()?;
+        if magic != LZ77_MAGIC {
+            io_bail!(
+                "unknown compression magic byte {:#04x}, expected {:#04x}",
+                magic,
+                LZ77_MAGIC
+            );
+        }
+
+        let mut size_buf = [0u8; 4];
+        reader.read_exact(&mut size_buf[..3])?;
+        let decompressed_len = u32::from_le_bytes(size_buf) as usize;
+
+        let mut out = Vec::with_capacity(decompressed_len);
+        while out.len() < decompressed_len {
+            let flags = reader.read_u8()?;
+            for bit in (0..8).rev() {
+                if out.len() >= decompressed_len {
+                    break;
+                }
+                if (flags >> bit) & 1 == 0 {
+                    out.push(reader.read_u8()?);
+                    continue;
+                }
+
+                let b1 = reader.read_u8()?;
+                let b2 = reader.read_u8()?;
+                let length = (b1 >> 4) as usize + 3;
+                let displacement = (((b1 & 0x0F) as usize) << 8 | b2 as usize) + 1;
+
+                let start = out.len().checked_sub(displacement).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "LZ77 displacement points before the start of the output",
+                    )
+                })?;
+                for src in start..start + length {
+                    if out.len() >= decompressed_len {
+                        break;
+                    }
+                    out.push(out[src]); // byte-by-byte copy, since runs may overlap.
+                }
+            }
+        }
+
+        Ok(Self {
+            cursor: io::Cursor::new(out),
+        })
+    }
+}
+
+impl Read for DecompressReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.cursor.read(buf)
+    }
+}
@@ -7,6 +7,7 @@ use std::assert_matches::assert_matches;
 use std::io;
 
 mod cartridge;
+mod compress;
 mod nitro;
 
 fn main() -> io::Result<()> {
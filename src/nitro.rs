@@ -1,10 +1,36 @@
 use crate::cartridge::{ReadBytes, ReadBytesExt};
+use crate::compress::DecompressReader;
 use crate::io_bail;
+use sha1::{Digest, Sha1};
 use std::collections::VecDeque;
 use std::fs;
 use std::io;
-use std::io::{Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom, Write};
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
 use std::path::{Path, PathBuf};
+use zerocopy::byteorder::little_endian::{U16, U32};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+
+/// The on-disk FAT entry for a file: its start/end offsets, relative to
+/// the image base.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct FatEntry {
+    start: U32,
+    end: U32,
+}
+
+/// The on-disk FNT main table entry for a directory.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct FntMainEntry {
+    sub_table_offset: U32,
+    first_file_id: U16,
+    /// The parent directory ID, except for the root's entry, whose
+    /// corresponding field instead holds the total number of directories.
+    _parent_or_total: U16,
+}
 
 /// An iterator that traverses the entries of a [filesystem](`Filesystem`)
 /// in pre-order.
@@ -83,9 +109,9 @@ impl Directory {
     /// On success, the file cursor is positioned at the end of the FNT sub-table
     /// of the directory.
     pub fn read(fs: &mut Filesystem, name: String) -> io::Result<Self> {
-        let sub_table_offset = fs.fnt_offset + fs.inner.read_u32()?;
-        let first_file_id = fs.inner.read_u16()?;
-        // We ignore the parent dir ID/total # of dirs fields.
+        let entry = FntMainEntry::read_from_io(&mut *fs.inner)?;
+        let sub_table_offset = fs.fnt_offset + entry.sub_table_offset.get();
+        let first_file_id = entry.first_file_id.get();
 
         fs.inner.seek(SeekFrom::Start(sub_table_offset as u64))?;
         Ok(Directory {
@@ -148,6 +174,161 @@ impl Directory {
         }
         None
     }
+
+    /// Recursively extracts the contents of the directory to `dest` on
+    /// the host filesystem, reading file contents through `fs`.
+    ///
+    /// Entry names containing a `..` component or an absolute path are
+    /// rejected, so that a crafted filesystem cannot write outside of
+    /// `dest`. Returns the number of files written.
+    pub fn extract_to<P: AsRef<Path>>(&self, fs: &mut Filesystem, dest: P) -> io::Result<usize> {
+        let dest = dest.as_ref();
+        fs::create_dir_all(dest)?;
+
+        let mut count = 0;
+        let mut curr_depth = 0;
+        let mut stack = PathBuf::new();
+        for (depth, entry) in self.traverse() {
+            match entry {
+                Entry::Directory(dir) => {
+                    check_safe_entry_name(dir.name())?;
+
+                    // Same bookkeeping as `search`: pop back to the common
+                    // parent level before descending into the directory's
+                    // own path. Only directories are yielded at a new
+                    // depth, so files below must leave `curr_depth` alone.
+                    while curr_depth >= depth {
+                        assert!(stack.pop());
+                        curr_depth -= 1;
+                    }
+                    stack.push(dir.name());
+                    curr_depth = depth;
+
+                    fs::create_dir_all(dest.join(&stack))?;
+                }
+                Entry::File(file) => {
+                    check_safe_entry_name(file.name())?;
+                    stack.push(file.name());
+
+                    let mut reader = fs.open(file);
+                    let mut out = fs::File::create(dest.join(&stack))?;
+                    io::copy(&mut reader, &mut out)?;
+                    count += 1;
+                    stack.pop();
+                }
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// Rejects entry names that could escape the destination directory when
+/// joined onto an extraction path.
+fn check_safe_entry_name(name: &str) -> io::Result<()> {
+    let path = Path::new(name);
+    let is_unsafe = path.is_absolute()
+        || path
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir));
+    if is_unsafe {
+        io_bail!("refusing to extract entry with unsafe path '{}'", name);
+    }
+    Ok(())
+}
+
+/// An entry yielded by [`DirReader::next`].
+///
+/// Unlike [`Entry`], the `Directory` variant only carries the metadata
+/// needed to descend into it via [`DirReader::open_dir`] — its sub-table
+/// is not read until the caller actually does so.
+#[derive(Debug)]
+pub enum LazyEntry {
+    File(File),
+    Directory { name: String, dir_id: u16 },
+}
+
+/// A cursor that lazily yields the entries of a single directory's FNT
+/// sub-table, one at a time, instead of eagerly materializing the whole
+/// subtree the way [`Directory::read`] does.
+///
+/// Constructed via [`Filesystem::root_dir_lazy`] or [`DirReader::open_dir`].
+/// Descending into a sub-directory only parses that sub-directory's own
+/// main table entry; sibling and descendant sub-tables that the caller
+/// never visits are never read.
+pub struct DirReader<'a, 'b> {
+    fs: &'b mut Filesystem<'a>,
+    sub_table_offset: u64,
+    first_file_id: u16,
+    file_id: u16,
+}
+
+impl<'a, 'b> DirReader<'a, 'b> {
+    fn at_dir(fs: &'b mut Filesystem<'a>, dir_id: u16) -> io::Result<Self> {
+        fs.inner.seek(SeekFrom::Start(fs.fnt_offset(dir_id)))?;
+        let entry = FntMainEntry::read_from_io(&mut *fs.inner)?;
+        let sub_table_offset = fs.fnt_offset as u64 + entry.sub_table_offset.get() as u64;
+        let first_file_id = entry.first_file_id.get();
+
+        fs.inner.seek(SeekFrom::Start(sub_table_offset))?;
+        Ok(Self {
+            fs,
+            sub_table_offset,
+            first_file_id,
+            file_id: first_file_id,
+        })
+    }
+
+    /// Re-seeks to the start of this directory's sub-table, so that the
+    /// next call to [`DirReader::next`] yields its first entry again.
+    pub fn rewind(&mut self) -> io::Result<()> {
+        self.file_id = self.first_file_id;
+        self.fs.inner.seek(SeekFrom::Start(self.sub_table_offset))?;
+        Ok(())
+    }
+
+    /// Returns the next entry of the directory, or `None` once the
+    /// sub-table is exhausted.
+    pub fn next(&mut self) -> io::Result<Option<LazyEntry>> {
+        loop {
+            let header = self.fs.inner.read_u8()?;
+            if header == 0 {
+                return Ok(None); // reached the end of the sub-table.
+            }
+            if header == 0x80 {
+                continue; // reserved.
+            }
+            let name = self.fs.inner.read_string((header & 0x7F) as usize)?;
+
+            let (entry, entry_end) = if (header & 0x80) == 0 {
+                // File entry: its ID is an offset into the FAT.
+                let entry_end = self.fs.inner.stream_position()?;
+                self.fs
+                    .inner
+                    .seek(SeekFrom::Start(self.fs.fat_offset(self.file_id)))?;
+                self.file_id += 1;
+
+                (LazyEntry::File(File::read(self.fs, name)?), entry_end)
+            } else {
+                // Sub-directory entry: don't descend, just record its ID.
+                let dir_id = self.fs.inner.read_u16()? & 0xFFF;
+                let entry_end = self.fs.inner.stream_position()?;
+
+                (LazyEntry::Directory { name, dir_id }, entry_end)
+            };
+
+            // Reading the FAT entry or the directory ID leaves the position
+            // of the file cursor unspecified. Restore it so that the next
+            // call to `next` reads the following entry correctly.
+            self.fs.inner.seek(SeekFrom::Start(entry_end))?;
+            return Ok(Some(entry));
+        }
+    }
+
+    /// Descends into a sub-directory entry previously yielded by
+    /// [`DirReader::next`], parsing only its own main table entry.
+    pub fn open_dir(self, dir_id: u16) -> io::Result<DirReader<'a, 'b>> {
+        DirReader::at_dir(self.fs, dir_id)
+    }
 }
 
 /// A file stored within a NitroROM filesystem.
@@ -164,11 +345,12 @@ impl File {
     /// The file cursor must point to the start of the FAT entry of the file.
     /// On success, the file cursor is positioned at the end of the FAT entry.
     pub fn read(fs: &mut Filesystem, name: String) -> io::Result<Self> {
-        let offset = fs.inner.read_u32()?; // with respect to image base
+        let entry = FatEntry::read_from_io(&mut *fs.inner)?;
+        let start = entry.start.get(); // with respect to image base
         Ok(Self {
             name,
-            offset: fs.image_offset + offset,
-            len: fs.inner.read_u32()? - offset, // todo: + 1?
+            offset: fs.image_offset + start,
+            len: entry.end.get() - start, // todo: + 1?
         })
     }
 
@@ -187,6 +369,85 @@ impl File {
     pub fn len(&self) -> u32 {
         self.len
     }
+
+    /// Computes the CRC32 checksum of the file contents, streaming them
+    /// through the hash rather than loading the whole file into memory.
+    pub fn crc32(&self, fs: &Filesystem) -> io::Result<u32> {
+        let mut hasher = crc32fast::Hasher::new();
+        stream_into(fs.open(self), |chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize())
+    }
+
+    /// Computes the SHA-1 digest of the file contents, streaming them
+    /// through the hash rather than loading the whole file into memory.
+    pub fn sha1(&self, fs: &Filesystem) -> io::Result<[u8; 20]> {
+        let mut hasher = Sha1::new();
+        stream_into(fs.open(self), |chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize().into())
+    }
+}
+
+/// Feeds the contents of `reader` to `sink` in fixed-size chunks.
+fn stream_into(mut reader: impl Read, mut sink: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        sink(&buf[..n]);
+    }
+}
+
+/// A reader over the contents of a [`File`], bounded to its byte range
+/// within the cartridge.
+///
+/// Returned by [`Filesystem::open`]. Reads and seeks are relative to the
+/// start of the file; reads stop at the end of the file's range rather than
+/// continuing into the rest of the cartridge. Seeking past the end is
+/// allowed, after which reads return zero bytes.
+pub struct EntryReader<'a> {
+    inner: &'a fs::File,
+    start: u64,
+    end: u64,
+    pos: u64,
+}
+
+impl<'a> EntryReader<'a> {
+    fn new(inner: &'a fs::File, file: &File) -> Self {
+        let start = file.offset() as u64;
+        Self {
+            inner,
+            start,
+            end: start + file.len() as u64,
+            pos: 0,
+        }
+    }
+}
+
+impl<'a> Read for EntryReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.end.saturating_sub(self.start + self.pos);
+        let len = (buf.len() as u64).min(remaining) as usize;
+        self.inner.read_exact_at(&mut buf[..len], self.start + self.pos)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<'a> Seek for EntryReader<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => (self.end - self.start) as i64 + offset,
+            SeekFrom::Current(offset) => self.pos as i64 + offset,
+        };
+        if new_pos < 0 {
+            io_bail!("attempted to seek before the start of the file");
+        }
+        self.pos = new_pos as u64;
+        Ok(self.pos)
+    }
 }
 
 /// A filesystem entry.
@@ -263,10 +524,19 @@ impl Entry {
     }
 }
 
+/// The on-disk header shared by every NARC chunk (`BTAF`/`BTNF`/`GMIF`).
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct NitroArcChunkHeader {
+    name: [u8; 4],
+    len: U32,
+}
+
 /// A Nitro Archive (NARC) chunk description.
 #[derive(Debug)]
 struct NitroArcChunk {
-    /// The offset within the cartridge at which the chunk starts.
+    /// The offset within the cartridge at which the chunk's payload starts,
+    /// i.e. just past its 8-byte magic/length header.
     offset: u32,
     /// The chunk length in bytes (including the header).
     len: u32,
@@ -277,8 +547,10 @@ impl NitroArcChunk {
     ///
     /// On success, the file cursor is positioned at the end of the chunk.
     pub fn read(file: &mut fs::File, name: &str) -> io::Result<Self> {
-        let offset = file.stream_position()? as u32;
-        let actual_name = file.read_string(4)?;
+        let chunk_start = file.stream_position()? as u32;
+        let header = NitroArcChunkHeader::read_from_io(&mut *file)?;
+        let actual_name = String::from_utf8(header.name.to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
         if actual_name != name {
             io_bail!(
                 "incorrect NARC chunk name '{}', expected '{}'",
@@ -286,15 +558,27 @@ impl NitroArcChunk {
                 name
             );
         }
-        let len = file.read_u32()?;
-        file.seek(SeekFrom::Start((offset + len) as u64))?; // skip contents.
+        let len = header.len.get();
+        file.seek(SeekFrom::Start((chunk_start + len) as u64))?; // skip contents.
         Ok(NitroArcChunk {
-            offset,
-            len: file.read_u32()?,
+            offset: chunk_start + 8,
+            len,
         })
     }
 }
 
+/// The on-disk header of a NARC file.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+struct NarcHeader {
+    signature: [u8; 4],
+    _byte_order: [u8; 2],
+    version: U16,
+    _file_size: U32,
+    _header_size: U16,
+    chunk_count: U16,
+}
+
 /// The contents of a NitroROM filesystem.
 #[derive(Debug)]
 pub struct Filesystem<'a> {
@@ -336,17 +620,18 @@ impl<'a> Filesystem<'a> {
     pub fn from_archive(file: &'a mut fs::File) -> io::Result<Self> {
         // A NARC is composed of a header and 3 chunks: the FAT, the FNT
         // and the image containing the file contents.
-        let file_sig = file.read_string(4)?;
-        if file_sig != "NARC" {
-            io_bail!("incorrect file signature '{}', expected 'NARC'", file_sig);
+        let header = NarcHeader::read_from_io(&mut *file)?;
+        if &header.signature != b"NARC" {
+            io_bail!(
+                "incorrect file signature '{}', expected 'NARC'",
+                String::from_utf8_lossy(&header.signature)
+            );
         }
-        file.skip(2)?; // byte order
-        let version = file.read_u16()?;
+        let version = header.version.get();
         if version != 0x10 {
             io_bail!("unknown NARC file version {}", version);
         }
-        file.skip(6)?; // skip file and header size
-        let chunk_count = file.read_u16()?;
+        let chunk_count = header.chunk_count.get();
         if chunk_count != 3 {
             io_bail!("NARC file has {} chunk, expected 3", chunk_count);
         }
@@ -375,6 +660,21 @@ impl<'a> Filesystem<'a> {
         self.fnt_offset as u64 + (dir_id as u64) * 8
     }
 
+    /// Returns a reader over the contents of a file, bounded to its
+    /// `[offset, offset + len)` byte range within the cartridge.
+    pub fn open(&self, file: &File) -> EntryReader {
+        EntryReader::new(&*self.inner, file)
+    }
+
+    /// Returns a reader over the decompressed contents of a file that is
+    /// stored using BIOS LZ77 (type `0x10`) compression.
+    ///
+    /// Returns an error if the file doesn't start with the LZ77 magic byte;
+    /// callers can fall back to [`Filesystem::open`] for such files.
+    pub fn open_decompressed(&self, file: &File) -> io::Result<DecompressReader> {
+        DecompressReader::new(self.open(file))
+    }
+
     /// Attempts to read the contents of the root directory.
     ///
     /// The file cursor position is unspecified upon return.
@@ -392,4 +692,277 @@ impl<'a> Filesystem<'a> {
         self.inner.seek(SeekFrom::Start(self.fnt_offset as u64))?;
         Directory::read(self, "root".to_string())
     }
+
+    /// Returns a lazy, streaming cursor over the entries of the root
+    /// directory.
+    ///
+    /// Unlike [`Filesystem::root_dir`], this doesn't parse any
+    /// sub-directories up front — see [`DirReader`].
+    pub fn root_dir_lazy(&mut self) -> io::Result<DirReader<'a, '_>> {
+        DirReader::at_dir(self, 0)
+    }
+
+    /// Attempts to locate the filesystem entry at the given path, without
+    /// materializing any part of the tree that isn't on the path.
+    ///
+    /// Each path component is resolved by reading only the sub-table of the
+    /// directory it belongs to and, for directory components, seeking
+    /// straight to the child's FNT main table entry via its sub-directory
+    /// ID. Sibling subtrees are never read, so this runs in time
+    /// proportional to the number of path components rather than the size
+    /// of the filesystem.
+    pub fn lookup<P: AsRef<Path>>(&mut self, path: P) -> io::Result<Option<Entry>> {
+        let mut components = path.as_ref().iter().peekable();
+        if components.peek().is_none() {
+            return Ok(None);
+        }
+
+        // Start at the root directory's main table entry.
+        let mut dir_id = 0u16;
+        while let Some(component) = components.next() {
+            let name = component
+                .to_str()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "non-UTF-8 path"))?;
+            let is_last = components.peek().is_none();
+
+            self.inner.seek(SeekFrom::Start(self.fnt_offset(dir_id)))?;
+            let entry = FntMainEntry::read_from_io(&mut *self.inner)?;
+            let sub_table_offset = self.fnt_offset + entry.sub_table_offset.get();
+            let mut file_id = entry.first_file_id.get();
+            self.inner.seek(SeekFrom::Start(sub_table_offset as u64))?;
+
+            let step = loop {
+                let header = self.inner.read_u8()?;
+                if header == 0 {
+                    break None; // reached the end of the sub-table without a match.
+                }
+                if header == 0x80 {
+                    continue; // reserved.
+                }
+                let entry_name = self.inner.read_string((header & 0x7F) as usize)?;
+                if (header & 0x80) == 0 {
+                    // File entry: its ID is an offset into the FAT.
+                    if entry_name == name {
+                        break Some((entry_name, None));
+                    }
+                    file_id += 1;
+                } else {
+                    // Sub-directory entry: its FNT main table ID follows the name.
+                    let subdir_id = self.inner.read_u16()? & 0xFFF;
+                    if entry_name == name {
+                        break Some((entry_name, Some(subdir_id)));
+                    }
+                }
+            };
+
+            match step {
+                None => return Ok(None),
+                Some((_, None)) if !is_last => return Ok(None), // file with children: no match.
+                Some((entry_name, None)) => {
+                    self.inner.seek(SeekFrom::Start(self.fat_offset(file_id)))?;
+                    return Ok(Some(Entry::File(File::read(self, entry_name)?)));
+                }
+                Some((entry_name, Some(subdir_id))) if is_last => {
+                    self.inner.seek(SeekFrom::Start(self.fnt_offset(subdir_id)))?;
+                    return Ok(Some(Entry::Directory(Directory::read(self, entry_name)?)));
+                }
+                Some((_, Some(subdir_id))) => dir_id = subdir_id,
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A file or directory staged for inclusion in a NARC archive being built
+/// by [`NarcBuilder`].
+#[derive(Debug)]
+enum BuildNode {
+    File { name: String, contents: Vec<u8> },
+    Directory { name: String, children: Vec<BuildNode> },
+}
+
+/// Builds a Nitro Archive (NARC) from files added by path, the write-side
+/// counterpart to [`Filesystem::from_archive`].
+///
+/// Directory IDs are assigned breadth-first starting at the root (ID `0`),
+/// and file IDs are assigned sequentially within each directory in that
+/// same order, mirroring how a NitroROM filesystem numbers its entries.
+#[derive(Debug, Default)]
+pub struct NarcBuilder {
+    root: Vec<BuildNode>,
+}
+
+impl NarcBuilder {
+    /// Returns an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stages a file at the given path, creating any missing parent
+    /// directories.
+    ///
+    /// As with [`Directory::extract_to`], path components containing `..`
+    /// or an absolute path are rejected.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P, contents: Vec<u8>) -> io::Result<()> {
+        let mut components = path.as_ref().iter().peekable();
+        let mut children = &mut self.root;
+        while let Some(component) = components.next() {
+            let name = component
+                .to_str()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "non-UTF-8 path"))?;
+            check_safe_entry_name(name)?;
+
+            if components.peek().is_none() {
+                children.push(BuildNode::File {
+                    name: name.to_string(),
+                    contents,
+                });
+                return Ok(());
+            }
+
+            let idx = children
+                .iter()
+                .position(|node| matches!(node, BuildNode::Directory { name: n, .. } if n == name))
+                .unwrap_or_else(|| {
+                    children.push(BuildNode::Directory {
+                        name: name.to_string(),
+                        children: Vec::new(),
+                    });
+                    children.len() - 1
+                });
+            children = match &mut children[idx] {
+                BuildNode::Directory { children, .. } => children,
+                BuildNode::File { .. } => {
+                    io_bail!("path component '{}' is a file, not a directory", name)
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Serializes the staged files into a NARC archive and writes it out.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        // A directory, as laid out for the FNT: its starting file ID, its
+        // parent ID (or, for the root, the total directory count), and its
+        // already-encoded sub-table.
+        struct DirRecord {
+            first_file_id: u16,
+            parent_or_total: u16,
+            sub_table: Vec<u8>,
+        }
+
+        // Walk the tree breadth-first, assigning directory IDs (root is
+        // always `0`) and, within each directory, sequential file IDs in
+        // the order files were added. This is the same layout
+        // `Entry::read_sub_table` and `Filesystem::lookup` expect.
+        let mut dirs = Vec::new();
+        let mut file_contents: Vec<&[u8]> = Vec::new();
+        let mut queue: VecDeque<(&[BuildNode], u16)> = VecDeque::new();
+        queue.push_back((self.root.as_slice(), 0));
+        let mut next_dir_id: u16 = 1;
+
+        while let Some((children, parent_id)) = queue.pop_front() {
+            let first_file_id = file_contents.len() as u16;
+            let mut sub_table = Vec::new();
+            for node in children {
+                match node {
+                    BuildNode::File { name, contents } => {
+                        sub_table.push(name.len() as u8);
+                        sub_table.extend_from_slice(name.as_bytes());
+                        file_contents.push(contents.as_slice());
+                    }
+                    BuildNode::Directory { name, children } => {
+                        let dir_id = next_dir_id;
+                        next_dir_id += 1;
+                        sub_table.push(0x80 | name.len() as u8);
+                        sub_table.extend_from_slice(name.as_bytes());
+                        // The high nibble is conventionally set to `0xF`
+                        // for sub-directory IDs stored in a sub-table.
+                        sub_table.extend_from_slice(&(0xF000u16 | dir_id).to_le_bytes());
+                        queue.push_back((children.as_slice(), dir_id));
+                    }
+                }
+            }
+            sub_table.push(0); // end of sub-table.
+            dirs.push(DirRecord {
+                first_file_id,
+                parent_or_total: parent_id,
+                sub_table,
+            });
+        }
+        // Unlike every other entry, the root's third field holds the total
+        // number of directories instead of a parent ID.
+        dirs[0].parent_or_total = dirs.len() as u16;
+
+        // Lay out the FNT main table followed by all sub-tables, in
+        // directory ID order.
+        let main_table_size = dirs.len() * 8;
+        let mut fnt = Vec::with_capacity(main_table_size);
+        let mut sub_table_offset = main_table_size as u32;
+        for (dir_id, dir) in dirs.iter().enumerate() {
+            // The root's field holds the unmasked total directory count;
+            // every other entry's parent ID has its high nibble set to
+            // `0xF`, matching the sub-directory IDs in the sub-tables.
+            let parent_or_total = if dir_id == 0 {
+                dir.parent_or_total
+            } else {
+                0xF000 | dir.parent_or_total
+            };
+            let entry = FntMainEntry {
+                sub_table_offset: U32::new(sub_table_offset),
+                first_file_id: U16::new(dir.first_file_id),
+                _parent_or_total: U16::new(parent_or_total),
+            };
+            fnt.extend_from_slice(entry.as_bytes());
+            sub_table_offset += dir.sub_table.len() as u32;
+        }
+        for dir in &dirs {
+            fnt.extend_from_slice(&dir.sub_table);
+        }
+
+        // Lay out the image area, aligning each file's contents to a
+        // 4-byte boundary, recording the FAT entries alongside it.
+        let mut image = Vec::new();
+        let mut fat = Vec::with_capacity(file_contents.len() * 8);
+        for contents in &file_contents {
+            while image.len() % 4 != 0 {
+                image.push(0);
+            }
+            let start = image.len() as u32;
+            image.extend_from_slice(contents);
+            let entry = FatEntry {
+                start: U32::new(start),
+                end: U32::new(image.len() as u32),
+            };
+            fat.extend_from_slice(entry.as_bytes());
+        }
+
+        let header_size = 0x10u16;
+        let file_size =
+            header_size as u32 + (8 + fat.len()) as u32 + (8 + fnt.len()) as u32 + (8 + image.len()) as u32;
+        let header = NarcHeader {
+            signature: *b"NARC",
+            _byte_order: [0xFF, 0xFE],
+            version: U16::new(0x10),
+            _file_size: U32::new(file_size),
+            _header_size: U16::new(header_size),
+            chunk_count: U16::new(3),
+        };
+        writer.write_all(header.as_bytes())?;
+        write_chunk(writer, b"BTAF", &fat)?;
+        write_chunk(writer, b"BTNF", &fnt)?;
+        write_chunk(writer, b"GMIF", &image)?;
+        Ok(())
+    }
+}
+
+/// Writes a single NARC chunk: its 8-byte magic/length header followed by
+/// `payload`.
+fn write_chunk<W: Write>(writer: &mut W, name: &[u8; 4], payload: &[u8]) -> io::Result<()> {
+    let header = NitroArcChunkHeader {
+        name: *name,
+        len: U32::new((8 + payload.len()) as u32),
+    };
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(payload)
 }